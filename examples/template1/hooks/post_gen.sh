@@ -0,0 +1,5 @@
+#!/bin/sh
+echo "Created template with myVar set to {{ myVar }}"
+{% if myBool %}
+echo "myBool was turned on"
+{% endif %}
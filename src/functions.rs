@@ -0,0 +1,128 @@
+use std::{collections::HashMap, env, process::Command};
+
+use chrono::{Local, Utc};
+use rand::{seq::SliceRandom, thread_rng};
+use tera::{from_value, to_value, Tera, Value};
+use uuid::Uuid;
+
+const ALPHANUMERIC: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const ALPHA: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const NUMERIC: &str = "0123456789";
+const HEX: &str = "0123456789abcdef";
+
+/// Registers the functions templates can use alongside `system()`.
+pub fn register_functions(tera: &mut Tera) {
+    tera.register_function("env", get_env);
+    tera.register_function("now", now);
+    tera.register_function("uuid", uuid);
+    tera.register_function("random_string", random_string);
+    tera.register_function("git_config", git_config);
+    tera.register_function("username", username);
+}
+
+fn get_arg<T: serde::de::DeserializeOwned>(args: &HashMap<String, Value>, name: &str) -> tera::Result<Option<T>> {
+    match args.get(name) {
+        Some(value) => from_value::<T>(value.clone())
+            .map(Some)
+            .map_err(|_| tera::Error::msg(format!("The {} argument has the wrong type!", name))),
+        None => Ok(None),
+    }
+}
+
+fn get_env(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let name = get_arg::<String>(args, "name")?.ok_or(tera::Error::msg("You need to pass in the name argument!"))?;
+
+    match env::var(&name) {
+        Ok(value) => Ok(to_value(value)?),
+        Err(_) => match args.get("default") {
+            Some(default) => Ok(default.clone()),
+            None => Err(tera::Error::msg(format!("Environment variable {} isn't set and no default was given!", name))),
+        },
+    }
+}
+
+fn now(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let format = get_arg::<String>(args, "format")?.unwrap_or("%Y-%m-%dT%H:%M:%S%:z".to_string());
+    let utc = get_arg::<bool>(args, "utc")?.unwrap_or(false);
+
+    let text = if utc {
+        Utc::now().format(&format).to_string()
+    } else {
+        Local::now().format(&format).to_string()
+    };
+
+    Ok(to_value(text)?)
+}
+
+fn uuid(_args: &HashMap<String, Value>) -> tera::Result<Value> {
+    Ok(to_value(Uuid::new_v4().to_string())?)
+}
+
+fn random_string(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let len = get_arg::<usize>(args, "len")?.unwrap_or(16);
+    let charset = get_arg::<String>(args, "charset")?.unwrap_or("alphanumeric".to_string());
+
+    //Named sets for the common cases, anything else is used as the list of characters to pick from
+    let chars: Vec<char> = match charset.as_str() {
+        "alphanumeric" => ALPHANUMERIC.chars().collect(),
+        "alpha" => ALPHA.chars().collect(),
+        "numeric" => NUMERIC.chars().collect(),
+        "hex" => HEX.chars().collect(),
+        _ => charset.chars().collect(),
+    };
+
+    if chars.is_empty() {
+        return Err(tera::Error::msg("The charset argument can't be empty!"));
+    }
+
+    let mut rng = thread_rng();
+    let text: String = (0..len).filter_map(|_| chars.choose(&mut rng)).collect();
+
+    Ok(to_value(text)?)
+}
+
+fn git_config(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let key = get_arg::<String>(args, "key")?.ok_or(tera::Error::msg("You need to pass in the key argument!"))?;
+
+    let output = Command::new("git").args(["config", "--get", &key]).output();
+
+    match output {
+        Ok(output) if output.status.success() => Ok(to_value(String::from_utf8_lossy(&output.stdout).trim())?),
+        _ => match args.get("default") {
+            Some(default) => Ok(default.clone()),
+            None => Ok(to_value("")?),
+        },
+    }
+}
+
+fn username(_args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let name = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .map_err(|_| tera::Error::msg("Can't work out the current user's name!"))?;
+
+    Ok(to_value(name)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tera::Context;
+
+    fn render(text: &str) -> String {
+        let mut tera = Tera::default();
+        register_functions(&mut tera);
+        tera.render_str(text, &Context::new()).unwrap()
+    }
+
+    #[test]
+    fn can_generate_values(){
+        assert_eq!(render(r#"{{ env(name="PREFAB_SURELY_NOT_SET", default="fallback") }}"#), "fallback");
+        assert_eq!(render(r#"{{ now(format="%Y", utc=true) }}"#).len(), 4);
+        assert_eq!(render("{{ uuid() }}").len(), 36);
+
+        let random = render(r#"{{ random_string(len=12, charset="hex") }}"#);
+        assert_eq!(random.len(), 12);
+        assert!(random.chars().all(|c| HEX.contains(c)));
+        assert_eq!(render(r#"{{ random_string(len=3, charset="x") }}"#), "xxx");
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use anyhow::{anyhow, Ok, Result};
+
+const WINDOWS_EXTENSIONS: [&str; 3] = ["ps1", "bat", "cmd"];
+const UNIX_EXTENSIONS: [&str; 2] = ["sh", "bash"];
+
+/// Finds the script for a hook stage (e.g. `pre_gen`) in the hooks folder of a template.
+/// When there is more than one script for the stage the one native to the current platform is preferred.
+pub fn find_script(template_path: impl AsRef<Path>, stage: &str) -> Result<Option<PathBuf>> {
+    let hooks_path = template_path.as_ref().join("hooks");
+
+    if !hooks_path.exists() {
+        return Ok(None);
+    }
+
+    let mut candidates = vec![];
+
+    for entry in fs::read_dir(hooks_path)? {
+        let path = entry?.path();
+
+        if path.is_file() && path.file_stem().and_then(|s| s.to_str()) == Some(stage) {
+            candidates.push(path);
+        }
+    }
+
+    candidates.sort();
+
+    let foreign = if cfg!(target_os = "windows") { &UNIX_EXTENSIONS[..] } else { &WINDOWS_EXTENSIONS[..] };
+    let native = candidates.iter().find(|p| {
+        let ext = get_extension(p);
+        !foreign.contains(&ext.as_str())
+    });
+
+    Ok(native.or(candidates.first()).cloned())
+}
+
+/// Works out the command line needed to run a script, first from its shebang and then from its extension.
+pub fn get_interpreter(path: impl AsRef<Path>, script: &str) -> Result<String> {
+    if let Some(shebang) = script.lines().next().and_then(|l| l.strip_prefix("#!")) {
+        let shebang = shebang.trim();
+
+        if !shebang.is_empty() {
+            if cfg!(target_os = "windows") {
+                //Unix paths won't exist on windows so fall back to looking the interpreter up on the path
+                let shebang = shebang.strip_prefix("/usr/bin/env ").unwrap_or(shebang);
+                let mut parts = shebang.splitn(2, ' ');
+                let program = parts.next().unwrap_or_default();
+                let program = program.rsplit('/').next().unwrap_or(program);
+
+                return Ok(match parts.next() {
+                    Some(args) => format!("{} {}", program, args),
+                    None => program.to_string(),
+                });
+            }
+
+            return Ok(shebang.to_string());
+        }
+    }
+
+    let interpreter = match get_extension(path.as_ref()).as_str() {
+        "sh" => "sh",
+        "bash" => "bash",
+        "py" => if cfg!(target_os = "windows") { "python" } else { "python3" },
+        "ps1" => "powershell -NoProfile -ExecutionPolicy Bypass -File",
+        "bat" | "cmd" => "cmd /C",
+        "js" => "node",
+        "rb" => "ruby",
+        "pl" => "perl",
+        _ => return Err(anyhow!(
+            "Can't work out how to run hook script {:?}! Add a shebang or a known file extension.",
+            path.as_ref()
+        )),
+    };
+
+    Ok(interpreter.to_string())
+}
+
+fn get_extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn can_pick_interpreter_from_shebang_or_extension(){
+        assert_eq!(get_interpreter("pre_gen", "#!/bin/bash\necho hi").unwrap(), if cfg!(target_os = "windows") { "bash" } else { "/bin/bash" });
+        assert_eq!(get_interpreter("pre_gen.rb", "puts 'hi'").unwrap(), "ruby");
+        assert!(get_interpreter("pre_gen", "echo hi").is_err());
+    }
+
+    #[test]
+    fn can_find_script_for_stage(){
+        let template_dir = TempDir::new().unwrap();
+        template_dir.child("hooks/pre_gen.ps1").touch().unwrap();
+        template_dir.child("hooks/pre_gen.sh").touch().unwrap();
+        template_dir.child("hooks/post_gen.py").touch().unwrap();
+
+        let pre = find_script(template_dir.path(), "pre_gen").unwrap().unwrap();
+        let post = find_script(template_dir.path(), "post_gen").unwrap().unwrap();
+
+        let expected = if cfg!(target_os = "windows") { "pre_gen.ps1" } else { "pre_gen.sh" };
+        assert_eq!(pre.file_name().unwrap(), expected);
+        assert_eq!(post.file_name().unwrap(), "post_gen.py");
+        assert!(find_script(template_dir.path(), "other").unwrap().is_none());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod template;
 pub mod config;
+mod functions;
+mod hooks;
+pub mod trust;
 mod util;
\ No newline at end of file
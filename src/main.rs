@@ -1,7 +1,8 @@
 mod tui;
 use anyhow::{anyhow, Ok, Result};
 use prefab::config::load_config;
-use tui::{run, setup_terminal};
+use prefab::trust::{find_commands, get_template_hash, TrustStore};
+use tui::{confirm_trust, run, setup_terminal};
 
 use core::panic;
 
@@ -79,6 +80,45 @@ fn get_template_directory() -> Result<PathBuf> {
     Err(anyhow!("Can't find a template directory!"))
 }
 
+/// Makes sure the user is happy for a template to run commands before anything is executed.
+fn check_trust(template: &Template, trust: bool, quiet: bool) -> Result<bool> {
+    let commands = find_commands(template)?;
+
+    if commands.is_empty() {
+        return Ok(true);
+    }
+
+    let hash = get_template_hash(&template.source_path)?;
+    let mut store = TrustStore::load(TrustStore::get_default_path()?)?;
+
+    if store.is_trusted(&hash) {
+        return Ok(true);
+    }
+
+    if !trust {
+        if quiet {
+            return Err(anyhow!(
+                "Template wants to run commands:\n{}\nRerun with --trust to allow this or --no-exec to refuse.",
+                commands.join("\n")
+            ));
+        }
+
+        let name = template.source_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut terminal = setup_terminal()?;
+        let result = confirm_trust(&mut terminal, &name, &commands);
+        restore_terminal(&mut terminal)?;
+
+        if !result? {
+            return Ok(false);
+        }
+    }
+
+    store.trust(&hash, &template.source_path);
+    store.save()?;
+
+    Ok(true)
+}
+
 fn main() {
     let matches = command!()
         .arg(arg!([name] "Template to use"))
@@ -90,6 +130,8 @@ fn main() {
         .arg(arg!(-c --config <FILE> "Sets a custom config file"))
         .arg(arg!(-l --list "Lists all of the templates installed on your system").group("list_group"))
         .arg(arg!(-d --dir <FOLDER> "Set the folder to create the result of the template in. Defaults to current directory."))
+        .arg(arg!(--trust "Trust the template to run its hooks and system() commands without asking").action(ArgAction::SetTrue))
+        .arg(arg!(--"no-exec" "Refuse to run any hooks or system() commands from the template").action(ArgAction::SetTrue).conflicts_with("trust"))
         .get_matches();
 
     let template_directory = if let Result::Ok(dir) = get_template_directory() {
@@ -122,10 +164,40 @@ fn main() {
 
         if let Result::Ok(tmp) = Template::load(buf) {
             let mut tmp = tmp;
+
+            if matches.get_flag("no-exec") {
+                tmp.set_allow_exec(false);
+            } else {
+                match check_trust(&tmp, matches.get_flag("trust"), matches.get_flag("quiet")) {
+                    Result::Ok(true) => {}
+                    Result::Ok(false) => {
+                        println!("Cancelled by user!");
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            if let Err(e) = tmp.render_defaults() {
+                eprintln!("{:#}", e);
+                std::process::exit(1);
+            }
+
             //Get config
 
-            let mut options = tmp.get_options();
-            
+            let resolve_path = if let Some(dir) = matches.get_one::<String>("dir") { dir.clone() } else { env::current_dir().unwrap().to_str().unwrap().to_string() };
+
+            let mut options = match tmp.run_prompt_hook(tmp.get_options(), &resolve_path) {
+                Result::Ok(options) => options,
+                Err(e) => {
+                    eprintln!("Prompt hook failed: {}", e);
+                    std::process::exit(1);
+                }
+            };
+
             if let Some(cfg) = matches.get_one::<String>("config") {
                 options = load_config( cfg, options).unwrap();
             }
@@ -162,8 +234,10 @@ fn main() {
 
             tmp.set_options(options);
 
-            let resolve_path = if let Some(dir) = matches.get_one::<String>("dir") { dir.clone() } else { env::current_dir().unwrap().to_str().unwrap().to_string() };
-            tmp.apply(resolve_path).unwrap();
+            if let Err(e) = tmp.apply(resolve_path) {
+                eprintln!("Failed to apply template: {:#}", e);
+                std::process::exit(1);
+            }
         }
     } else {
         println!("Expected you to pass the name of a template in.");
@@ -1,41 +1,55 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
+    io::Write,
     path::{Path, PathBuf}, env::current_dir,
+    sync::Mutex,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Ok, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
 use tera::{Context, Tera, from_value, to_value};
 
-use crate::util::{copy_all, exec};
+use crate::functions::register_functions;
+use crate::hooks::{find_script, get_interpreter};
+use crate::util::{copy_all, exec_with, ExecError, ExecOptions};
 use regex::Regex as re;
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub enum TemplateOption {
     FreeText {
         prompt: String,
         value: Option<String>,
         #[serde(default)]
         mandatory: bool,
+        #[serde(default)]
+        hidden: bool,
     },
     Boolean {
         prompt: String,
         value: Option<bool>,
         #[serde(default)]
         mandatory: bool,
+        #[serde(default)]
+        hidden: bool,
     },
     Integer {
         prompt: String,
         value: Option<i64>,
         #[serde(default)]
         mandatory: bool,
+        #[serde(default)]
+        hidden: bool,
     },
     Float {
         prompt: String,
         value: Option<f64>,
         #[serde(default)]
         mandatory: bool,
+        #[serde(default)]
+        hidden: bool,
     },
     Regex {
         prompt: String,
@@ -43,6 +57,8 @@ pub enum TemplateOption {
         value: Option<String>,
         #[serde(default)]
         mandatory: bool,
+        #[serde(default)]
+        hidden: bool,
     },
     Choice {
         prompt: String,
@@ -50,6 +66,19 @@ pub enum TemplateOption {
         value: Option<String>,
         #[serde(default)]
         mandatory: bool,
+        #[serde(default)]
+        hidden: bool,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Hook {
+    Command(String),
+    Detailed {
+        command: String,
+        /// Seconds to wait before the hook and any processes it started are killed.
+        timeout: Option<u64>,
     },
 }
 
@@ -60,16 +89,26 @@ pub struct Manifest {
     pub author: Option<String>,
     #[allow(dead_code)]
     pub description: Option<String>,
-    before_hook: Option<String>,
-    after_hook: Option<String>,
+    prompt_hook: Option<Hook>,
+    before_hook: Option<Hook>,
+    after_hook: Option<Hook>,
     options: HashMap<String, TemplateOption>,
 }
 
+/// Changes a prompt hook can make to an option before the user is asked for it.
+#[derive(Deserialize, Debug, Default)]
+pub struct OptionOverride {
+    pub value: Option<tera::Value>,
+    pub options: Option<Vec<String>>,
+    pub hidden: Option<bool>,
+}
+
 #[derive(Debug)]
 pub struct Template {
     tera: Tera,
     pub manifest: Manifest,
     pub source_path: PathBuf,
+    allow_exec: bool,
 }
 
 impl TemplateOption {
@@ -90,79 +129,70 @@ impl TemplateOption {
         }
     }
 
-    pub fn set_value(self, text: String)-> TemplateOption {
-        match self {
-            TemplateOption::FreeText { prompt, value:_, mandatory } => {
+    pub fn set_value(mut self, text: String)-> TemplateOption {
+        match &mut self {
+            TemplateOption::FreeText { value, mandatory, .. } => {
                 if !text.is_empty() {
-                    TemplateOption::FreeText { prompt, value: Some(text), mandatory: false }
+                    *value = Some(text);
+                    *mandatory = false;
                 }else{
-                    TemplateOption::FreeText { prompt, value: None, mandatory }
+                    *value = None;
                 }
             },
-            TemplateOption::Boolean { prompt, value:_, mandatory } => {
+            TemplateOption::Boolean { value, .. } => {
                 let text = text.trim().to_lowercase();
-                if text == "true" {
-                    return TemplateOption::Boolean { prompt, value: Some(true), mandatory }
-                }
 
                 if text.is_empty() {
-                    return TemplateOption::Boolean { prompt, value: None, mandatory };
+                    *value = None;
+                } else {
+                    *value = Some(text == "true");
                 }
-
-                TemplateOption::Boolean { prompt, value: Some(false), mandatory }
             },
-            TemplateOption::Integer { prompt, value, mandatory } => {
-                let result: Result<i64, _> = text.parse();
-
+            TemplateOption::Integer { value, .. } => {
                 if text.is_empty() {
-                    return TemplateOption::Integer { prompt, value: None, mandatory };
+                    *value = None;
+                } else if let Result::Ok(num) = text.parse::<i64>() {
+                    *value = Some(num);
                 }
-
-                 match result {
-                    Result::Ok(num) => {
-                        TemplateOption::Integer { prompt, value: Some(num), mandatory }
-                    },
-                    Err(_) => {
-                        TemplateOption::Integer { prompt, value, mandatory }
-                    },
-                }                
             },
-            TemplateOption::Float { prompt, value, mandatory } => {
+            TemplateOption::Float { value, .. } => {
                 let mut text = text;
                 if text.ends_with('.') {
                     text += "0";
                 }
 
-                let result: Result<f64, _> = text.parse();
-
                 if text.is_empty() {
-                    return TemplateOption::Float { prompt, value: None, mandatory };
+                    *value = None;
+                } else if let Result::Ok(num) = text.parse::<f64>() {
+                    *value = Some(num);
                 }
-
-                 match result {
-                    Result::Ok(num) => {
-                        TemplateOption::Float { prompt, value: Some(num), mandatory }
-                    },
-                    Err(_) => {
-                        TemplateOption::Float { prompt, value, mandatory }
-                    },
-                }  
             },
-            TemplateOption::Regex { prompt, pattern, value:_, mandatory } => {
-                if !text.is_empty() {
-                    TemplateOption::Regex { prompt, pattern, value: Some(text), mandatory }
-                }else{
-                    TemplateOption::Regex { prompt, pattern, value: None, mandatory }
-                }
+            TemplateOption::Regex { value, .. } => {
+                *value = if text.is_empty() { None } else { Some(text) };
             },
-            TemplateOption::Choice { prompt, options, value:_, mandatory } => {
-                if !text.is_empty() {
-                    TemplateOption::Choice { prompt, options, value: Some(text), mandatory }
-                }else{
-                    TemplateOption::Choice { prompt, options, value: None, mandatory }
-                }
+            TemplateOption::Choice { value, .. } => {
+                *value = if text.is_empty() { None } else { Some(text) };
             },
-        }       
+        }
+
+        self
+    }
+
+    pub fn set_choice_options(&mut self, choices: Vec<String>) {
+        if let TemplateOption::Choice { options, .. } = self {
+            *options = choices;
+        }
+    }
+
+    pub fn set_hidden(&mut self, hide: bool) {
+        match self {
+            TemplateOption::FreeText { hidden, .. } => *hidden = hide,
+            TemplateOption::Boolean { hidden, .. } => *hidden = hide,
+            TemplateOption::Integer { hidden, .. } => *hidden = hide,
+            TemplateOption::Float { hidden, .. } => *hidden = hide,
+            TemplateOption::Regex { hidden, .. } => *hidden = hide,
+            TemplateOption::Choice { hidden, .. } => *hidden = hide,
+        }
     }
 
     pub fn get_pattern(&self) -> Option<String> {
@@ -201,6 +231,17 @@ impl TemplateOption {
         }
     }
 
+    pub fn is_hidden(&self) -> bool {
+        match self {
+            TemplateOption::FreeText { hidden, .. } => *hidden,
+            TemplateOption::Boolean { hidden,.. } => *hidden,
+            TemplateOption::Integer { hidden,.. } => *hidden,
+            TemplateOption::Float { hidden,.. } => *hidden,
+            TemplateOption::Regex { hidden,.. } => *hidden,
+            TemplateOption::Choice { hidden,.. } => *hidden,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         match self{
             TemplateOption::FreeText { value, .. } => value.is_none(),
@@ -213,7 +254,41 @@ impl TemplateOption {
     }
 }
 
-impl Manifest { 
+impl Hook {
+    pub fn get_command(&self) -> &str {
+        match self {
+            Hook::Command(command) => command,
+            Hook::Detailed { command, .. } => command,
+        }
+    }
+
+    pub fn get_timeout(&self) -> Option<Duration> {
+        match self {
+            Hook::Command(_) => None,
+            Hook::Detailed { timeout, .. } => timeout.map(Duration::from_secs),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn get_hooks(&self) -> Vec<(&'static str, &Hook)> {
+        let mut result = vec![];
+
+        if let Some(hook) = &self.prompt_hook {
+            result.push(("prompt_hook", hook));
+        }
+
+        if let Some(hook) = &self.before_hook {
+            result.push(("before_hook", hook));
+        }
+
+        if let Some(hook) = &self.after_hook {
+            result.push(("after_hook", hook));
+        }
+
+        result
+    }
+
     fn validate_options(&self) -> Result<()> {
 
         for (k, v) in &self.options {
@@ -226,10 +301,8 @@ impl Manifest {
                         return Err(anyhow!("Regular expression for {} didn't match!", k));
                     }
                 },
-                TemplateOption::Choice { options, value: Some(value), .. } => {
-                    if !options.contains(value) {
-                        return Err(anyhow!("Option not in the choice list for {} was set.", k));
-                    }
+                TemplateOption::Choice { options, value: Some(value), .. } if !options.contains(value) => {
+                    return Err(anyhow!("Option not in the choice list for {} was set.", k));
                 },
                 _ => ()
             }
@@ -239,25 +312,121 @@ impl Manifest {
     }
 }
 
-fn system(args: &HashMap<String, tera::Value>) -> Result<tera::Value, tera::Error> {
+/// The `system()` Tera function. Identical calls are only run once for each apply.
+struct SystemFunction {
+    template_path: PathBuf,
+    destination_path: PathBuf,
+    cache: Mutex<HashMap<String, String>>,
+}
+
+impl SystemFunction {
+    fn new(template_path: impl AsRef<Path>, destination_path: impl AsRef<Path>) -> SystemFunction {
+        SystemFunction {
+            template_path: template_path.as_ref().to_path_buf(),
+            destination_path: destination_path.as_ref().to_path_buf(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_working_directory(&self, cwd: Option<&tera::Value>) -> Result<PathBuf, tera::Error> {
+        let cwd = match cwd {
+            Some(cwd) => from_value::<String>(cwd.clone())?,
+            None => "destination".to_string(),
+        };
+
+        match cwd.as_str() {
+            //The destination might not have been created yet
+            "destination" if self.destination_path.exists() => Result::Ok(self.destination_path.clone()),
+            "destination" => Result::Ok(current_dir()?),
+            "template" => Result::Ok(self.template_path.clone()),
+            _ => Err(tera::Error::msg(format!("cwd must be \"destination\" or \"template\" but was \"{}\"!", cwd))),
+        }
+    }
+
+    fn run(&self, args: &HashMap<String, tera::Value>) -> Result<String, tera::Error> {
+        let command = match args.get("command") {
+            Some(command) => from_value::<String>(command.clone())?,
+            None => return Err(tera::Error::msg("You need to pass in the command argument!")),
+        };
+
+        let working_directory = self.get_working_directory(args.get("cwd"))?;
+
+        let mut env = HashMap::<String, String>::new();
+
+        //Tera has no object literals so variables can also be passed as a list of KEY=VALUE strings
+        match args.get("env") {
+            Some(tera::Value::Object(vars)) => {
+                for (k, v) in vars {
+                    let value = match v {
+                        tera::Value::String(text) => text.clone(),
+                        _ => v.to_string(),
+                    };
+
+                    env.insert(k.clone(), value);
+                }
+            },
+            Some(tera::Value::Array(vars)) => {
+                for var in vars {
+                    let var = from_value::<String>(var.clone())?;
+                    let (k, v) = var.split_once('=').ok_or(tera::Error::msg(format!("env entry {} needs to be in the form KEY=VALUE!", var)))?;
+                    env.insert(k.to_string(), v.to_string());
+                }
+            },
+            Some(_) => return Err(tera::Error::msg("The env argument of system() must be an object or a list of KEY=VALUE strings!")),
+            None => {},
+        }
+
+        let options = ExecOptions {
+            stdin: args.get("stdin").map(|s| from_value::<String>(s.clone())).transpose()?,
+            ..ExecOptions::default()
+        };
+
+        let allow_failure = args.get("allow_failure").map(|a| from_value::<bool>(a.clone())).transpose()?.unwrap_or(false);
+
+        match exec_with(&command, env, working_directory, &options) {
+            Result::Ok(output) => Result::Ok(output),
+            Err(e) if allow_failure => match e.downcast::<ExecError>() {
+                Result::Ok(ExecError::Failed { stdout, .. }) => Result::Ok(stdout),
+                _ => Result::Ok(String::new()),
+            },
+            Err(e) => Err(tera::Error::msg(format!("system() failed! {}", e))),
+        }
+    }
+}
+
+impl tera::Function for SystemFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> Result<tera::Value, tera::Error> {
+        //Sorted so the same arguments always give the same key. Trimming happens after so it isn't part of it.
+        let key = serde_json::to_string(&args.iter().filter(|(k, _)| *k != "trim").collect::<BTreeMap<_, _>>())?;
+        let cached = self.cache.lock().map_err(|_| tera::Error::msg("system() cache was poisoned!"))?.get(&key).cloned();
+
+        let output = match cached {
+            Some(output) => output,
+            None => {
+                let output = self.run(args)?;
+                self.cache.lock().map_err(|_| tera::Error::msg("system() cache was poisoned!"))?.insert(key, output.clone());
+                output
+            }
+        };
 
-    if let Some(command) = args.get("command"){
-        if let Result::Ok(command) = from_value::<String>(command.clone()){
-            return if let Result::Ok(result) = exec(command.as_str(), HashMap::<String, String>::new(), current_dir()?) {
-                Result::Ok(to_value::<String>(result)?)
-            } else {
-                Err(tera::Error::msg("Failed to run command!"))
-            }   
+        let trim = args.get("trim").map(|t| from_value::<bool>(t.clone())).transpose()?.unwrap_or(false);
+
+        if trim {
+            Result::Ok(to_value(output.trim())?)
+        } else {
+            Result::Ok(to_value(output)?)
         }
     }
-    
-    Err(tera::Error::msg("You need to pass in the command argument!"))
+}
+
+fn refuse_system(_args: &HashMap<String, tera::Value>) -> Result<tera::Value, tera::Error> {
+    Err(tera::Error::msg("system() can't be used because running commands has been disabled!"))
 }
 
 impl Template {
     fn render_file_path(&mut self, path: impl AsRef<Path>) -> Result<String> {
         let ctx = self.get_context();
-        if let Some(path) = path.as_ref().clone().to_str(){
+        if let Some(path) = path.as_ref().to_str(){
             Ok(self.tera.render_str(path, &ctx)?)
         }else{
             Err(anyhow!("Failed to process path!"))
@@ -293,13 +462,18 @@ impl Template {
             tera = Tera::new(tera_dir)?;
         }
 
-        tera.register_function("system", system);
+        register_functions(&mut tera);
 
-        Ok(Template {
+        let mut template = Template {
             tera,
             manifest: toml,
             source_path: path.as_ref().to_path_buf(),
-        })
+            allow_exec: true,
+        };
+
+        template.register_system(current_dir()?);
+
+        Ok(template)
     }
 
     fn get_context(&self) -> Context {
@@ -343,18 +517,127 @@ impl Template {
         ctx
     }
 
+    fn get_hook_environment(&self, path: impl AsRef<Path>) -> HashMap<String, String> {
+        let mut env = HashMap::<String, String>::new();
+
+        env.insert("PREFAB_TEMPLATE".to_string(), self.source_path.to_string_lossy().to_string());
+        env.insert("PREFAB_DEST".to_string(), path.as_ref().to_string_lossy().to_string());
+
+        let template_name = self.source_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        env.insert("PREFAB_TEMPLATE_NAME".to_string(), template_name);
+
+        for (name, opt) in &self.manifest.options {
+            let var: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+                .collect();
+
+            env.insert(format!("PREFAB_OPT_{}", var), opt.get_value().unwrap_or_default());
+        }
+
+        env
+    }
+
+    fn run_hook(&self, name: &str, hook: &Hook, path: impl AsRef<Path>) -> Result<()> {
+        self.run_command(name, hook.get_command(), hook.get_timeout(), path)
+    }
+
+    fn run_script_hook(&mut self, stage: &str, path: impl AsRef<Path>) -> Result<()> {
+        let script_path = if let Some(script_path) = find_script(&self.source_path, stage)? {
+            script_path
+        } else {
+            return Ok(());
+        };
+
+        self.check_allow_exec(stage, &script_path.to_string_lossy())?;
+
+        let ctx = self.get_context();
+        let script = self.tera.render_str(&fs::read_to_string(&script_path)?, &ctx)?;
+        let interpreter = get_interpreter(&script_path, &script)?;
+
+        //Keep the extension so interpreters that care about it (e.g. powershell) still run the script
+        let suffix = script_path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let mut script_file = tempfile::Builder::new().prefix(stage).suffix(&suffix).tempfile()?;
+        script_file.write_all(script.as_bytes())?;
+        script_file.flush()?;
+
+        let command = format!("{} \"{}\"", interpreter, script_file.path().to_string_lossy());
+        self.run_command(stage, &command, None, path)
+    }
+
+    fn check_allow_exec(&self, name: &str, command: &str) -> Result<()> {
+        if !self.allow_exec {
+            return Err(anyhow!("Refusing to run {} `{}` because running commands has been disabled!", name, command));
+        }
+
+        Ok(())
+    }
+
+    /// Turns running hooks and the `system()` function on or off.
+    pub fn set_allow_exec(&mut self, allow: bool) {
+        self.allow_exec = allow;
+        self.register_system(current_dir().unwrap_or_default());
+    }
+
+    /// Registers `system()` for a destination which also starts it with an empty cache.
+    fn register_system(&mut self, destination: impl AsRef<Path>) {
+        if self.allow_exec {
+            self.tera.register_function("system", SystemFunction::new(&self.source_path, destination));
+        } else {
+            self.tera.register_function("system", refuse_system);
+        }
+    }
+
+    fn run_command(&self, name: &str, command: &str, timeout: Option<Duration>, path: impl AsRef<Path>) -> Result<()> {
+        self.check_allow_exec(name, command)?;
+        let mut env = self.get_hook_environment(&path);
+
+        //Keep the context file alive until the hook has finished with it
+        let mut context_file = NamedTempFile::new()?;
+        context_file.write_all(self.get_context().into_json().to_string().as_bytes())?;
+        env.insert("PREFAB_CONTEXT_FILE".to_string(), context_file.path().to_string_lossy().to_string());
+
+        let options = ExecOptions {
+            prefix: Some(name.to_string()),
+            timeout,
+            ..ExecOptions::default()
+        };
+
+        println!("Running {}: {}", name, command);
+        exec_with(command, env, &path, &options)?;
+
+        Ok(())
+    }
+
     pub fn apply(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.register_system(&path);
+
         let ctx = self.get_context();
         let names: Vec<String> = self.tera.get_template_names().map(|n| n.to_string()).collect();
 
         self.manifest.validate_options()?;
 
+        //Refuse up front rather than leave a half generated template behind
+        if let Some(hook) = &self.manifest.after_hook {
+            self.check_allow_exec("after_hook", hook.get_command())?;
+        }
+
+        if let Some(script) = find_script(&self.source_path, "post_gen")? {
+            self.check_allow_exec("post_gen", &script.to_string_lossy())?;
+        }
+
         if let Some(hook) = &self.manifest.before_hook {
-            let mut env = HashMap::<String, String>::new();
-            env.insert("PREFAB_TEMPLATE".to_string(), self.source_path.to_string_lossy().to_string());
-            exec(hook, env, &path)?;
+            self.run_hook("before_hook", hook, &path)?;
         }
 
+        self.run_script_hook("pre_gen", &path)?;
+
         for tmpl in names {
             let text = self.tera.render(&tmpl, &ctx)?;
             let mut file_name = PathBuf::new();
@@ -384,10 +667,85 @@ impl Template {
             copy_all(static_folder_path, &path, &mut self.tera, &ctx)?;
         }
 
+        self.run_script_hook("post_gen", &path)?;
+
         if let Some(hook) = &self.manifest.after_hook {
-            let mut env = HashMap::<String, String>::new();
-            env.insert("PREFAB_TEMPLATE".to_string(), self.source_path.to_string_lossy().to_string());
-            exec(hook, env, &path)?;
+            self.run_hook("after_hook", hook, &path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the prompt hook (if there is one) passing it the options as JSON on stdin.
+    /// The hook can print JSON back to change defaults, replace choice lists or hide options.
+    pub fn run_prompt_hook(&self, mut options: HashMap<String, TemplateOption>, path: impl AsRef<Path>) -> Result<HashMap<String, TemplateOption>> {
+        let hook = if let Some(hook) = &self.manifest.prompt_hook {
+            hook
+        } else {
+            return Ok(options);
+        };
+
+        self.check_allow_exec("prompt_hook", hook.get_command())?;
+
+        let working_directory = if path.as_ref().exists() { path.as_ref().to_path_buf() } else { current_dir()? };
+        let exec_options = ExecOptions {
+            timeout: hook.get_timeout(),
+            stdin: Some(serde_json::to_string(&options)?),
+            ..ExecOptions::default()
+        };
+
+        let output = exec_with(hook.get_command(), self.get_hook_environment(&path), working_directory, &exec_options)?;
+
+        if output.trim().is_empty() {
+            return Ok(options);
+        }
+
+        let overrides = serde_json::from_str::<HashMap<String, OptionOverride>>(&output)
+            .map_err(|e| anyhow!("Prompt hook returned invalid JSON! {}", e))?;
+
+        for (name, change) in overrides {
+            let opt = options
+                .remove(&name)
+                .ok_or(anyhow!("Prompt hook changed {} which isn't in the manifest!", name))?;
+
+            let mut opt = match change.value {
+                Some(tera::Value::String(text)) => opt.set_value(text),
+                Some(tera::Value::Null) => opt.set_value("".to_string()),
+                Some(value) => opt.set_value(value.to_string()),
+                None => opt,
+            };
+
+            if let Some(choices) = change.options {
+                opt.set_choice_options(choices);
+            }
+
+            if let Some(hidden) = change.hidden {
+                opt.set_hidden(hidden);
+            }
+
+            options.insert(name, opt);
+        }
+
+        Ok(options)
+    }
+
+    /// Renders option values that use Tera so defaults can come from functions like `git_config()`.
+    pub fn render_defaults(&mut self) -> Result<()> {
+        let ctx = Context::new();
+
+        for (name, opt) in self.manifest.options.iter_mut() {
+            let value = match opt {
+                TemplateOption::FreeText { value: Some(value), .. } => value,
+                TemplateOption::Regex { value: Some(value), .. } => value,
+                TemplateOption::Choice { value: Some(value), .. } => value,
+                _ => continue,
+            };
+
+            if value.contains("{{") || value.contains("{%") {
+                *value = self.tera
+                    .render_str(value, &ctx)
+                    .map_err(|e| anyhow!("Failed to render default for {}! {:#}", name, anyhow::Error::from(e)))?;
+            }
         }
 
         Ok(())
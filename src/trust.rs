@@ -0,0 +1,112 @@
+use std::{collections::BTreeMap, env, fs, path::{Path, PathBuf}};
+
+use anyhow::{Ok, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::hooks::find_script;
+use crate::template::Template;
+use crate::util::list_files;
+
+/// Hashes the names and contents of every file in a template so any change to it has to be trusted again.
+pub fn get_template_hash(path: impl AsRef<Path>) -> Result<String> {
+    let mut hasher = Sha256::new();
+
+    for file in list_files(&path)? {
+        hasher.update(file.to_string_lossy().replace('\\', "/").as_bytes());
+        hasher.update([0]);
+        hasher.update(fs::read(path.as_ref().join(&file))?);
+        hasher.update([0]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Lists every command a template can run: its hooks, hook scripts and calls to `system()`.
+pub fn find_commands(template: &Template) -> Result<Vec<String>> {
+    let mut result = vec![];
+
+    for (name, hook) in template.manifest.get_hooks() {
+        result.push(format!("{}: {}", name, hook.get_command()));
+    }
+
+    for stage in ["pre_gen", "post_gen"] {
+        if let Some(script) = find_script(&template.source_path, stage)? {
+            let script = script.strip_prefix(&template.source_path).unwrap_or(&script).to_path_buf();
+            result.push(format!("{}: {}", stage, script.to_string_lossy()));
+        }
+    }
+
+    let system_call = Regex::new(r"system\s*\(([^)]*)\)")?;
+
+    for file in list_files(&template.source_path)? {
+        let name = file.to_string_lossy().replace('\\', "/");
+
+        //Static files are copied as is so only their names are rendered
+        let text = if name.starts_with("static/") {
+            name.clone()
+        } else {
+            format!("{}\n{}", name, String::from_utf8_lossy(&fs::read(template.source_path.join(&file))?))
+        };
+
+        for call in system_call.captures_iter(&text) {
+            result.push(format!("system() in {}: {}", name, call[1].trim()));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Hashes of templates the user has agreed to let run commands.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TrustStore {
+    #[serde(default)]
+    trusted: BTreeMap<String, String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl TrustStore {
+    pub fn get_default_path() -> Result<PathBuf> {
+        let mut path = PathBuf::new();
+
+        if cfg!(target_os = "windows") {
+            path.push(env::var("USERPROFILE")?);
+            path.push(".prefab/trusted.toml");
+        } else {
+            path.push(env::var("HOME")?);
+            path.push(".config/prefab/trusted.toml");
+        }
+
+        Ok(path)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<TrustStore> {
+        let mut store = if path.as_ref().exists() {
+            toml::from_str::<TrustStore>(&fs::read_to_string(&path)?)?
+        } else {
+            TrustStore::default()
+        };
+
+        store.path = path.as_ref().to_path_buf();
+        Ok(store)
+    }
+
+    pub fn is_trusted(&self, hash: &str) -> bool {
+        self.trusted.contains_key(hash)
+    }
+
+    pub fn trust(&mut self, hash: &str, source: impl AsRef<Path>) {
+        self.trusted.insert(hash.to_string(), source.as_ref().to_string_lossy().to_string());
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(folder) = self.path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        fs::write(&self.path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
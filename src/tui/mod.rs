@@ -3,6 +3,7 @@ mod text;
 mod boolean;
 mod number;
 mod choice;
+mod trust;
 
 use std::{io::{Stdout, self}, time::Duration, collections::HashMap};
 use crossterm::{terminal::{enable_raw_mode, EnterAlternateScreen, disable_raw_mode, LeaveAlternateScreen}, execute, event::{self, Event, KeyCode}};
@@ -35,6 +36,10 @@ pub fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, options: &mut Hash
     option_menu(terminal, options)
 }
 
+pub fn confirm_trust(terminal: &mut Terminal<CrosstermBackend<Stdout>>, name: &str, commands: &[String]) -> Result<bool> {
+    trust::trust_menu(terminal, name, commands)
+}
+
 fn option_menu(terminal: &mut Terminal<CrosstermBackend<Stdout>>, options: &mut HashMap<String, TemplateOption>) -> Result<bool> {
     let mut state = ListState::default();
     state.select(Some(0));
@@ -113,7 +118,7 @@ fn option_menu(terminal: &mut Terminal<CrosstermBackend<Stdout>>, options: &mut
                     if let Some(index) = state.selected() {
                         let mut index = index;
 
-                        if index >= options.len() {
+                        if index >= elements.len() {
                             index = 0;
                         } else {
                             index += 1;
@@ -125,8 +130,6 @@ fn option_menu(terminal: &mut Terminal<CrosstermBackend<Stdout>>, options: &mut
 
                 if KeyCode::Enter == key.code {
                     if let Some(index) = state.selected() {
-                        let index = index;
-
                         if index == elements.len() && not_ready {
                             continue;
                         }
@@ -159,13 +162,9 @@ fn option_menu(terminal: &mut Terminal<CrosstermBackend<Stdout>>, options: &mut
     }
 }
 
-fn apply_elements_to_options(options:&mut HashMap<String, TemplateOption>, elements: &Vec<Box<dyn OptionUi>>) {
-    let opts =options.clone();
-    let keys: Vec<&String> = opts.keys().collect();
-
-    for i in 0..keys.len() {
-        let op = options.get_mut(keys[i]).unwrap();
-        *op = elements[i].get_option().clone();
+fn apply_elements_to_options(options:&mut HashMap<String, TemplateOption>, elements: &[Box<dyn OptionUi>]) {
+    for element in elements {
+        options.insert(element.get_name(), element.get_option());
     }
 }
 
@@ -174,6 +173,10 @@ fn get_elements(options: &HashMap<String, TemplateOption>) -> Result<Vec<Box<dyn
     for k in options.keys() {
         let opt = options.get(k).unwrap();
 
+        if opt.is_hidden() {
+            continue;
+        }
+
         result.push(match opt{
             TemplateOption::FreeText { .. } => Box::new(TextUI::new(opt.clone(), k.clone())),
             TemplateOption::Boolean { .. } => Box::new(BooleanUI::new(opt.clone(), k.clone())),
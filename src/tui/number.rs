@@ -83,16 +83,16 @@ impl OptionUi for NumberUI {
                     self.status = EditorStatus::Cancel;
                     return Ok(())
                 } else if KeyCode::Enter == key.code {
-                    let v = self.input.value().clone().to_string();
+                    let v = self.input.value().to_string();
                     let o = self.option.clone().set_value(v);
                     self.option = o.clone();
                     self.status = EditorStatus::Finished { option: o };
                     return Ok(());
                 } else {
-                    let orig = self.input.value().clone().to_string();
+                    let orig = self.input.value().to_string();
 
                     self.input.handle_event(&Event::Key(key));
-                    let v = self.input.value().clone().to_string();
+                    let v = self.input.value().to_string();
 
                     if v.is_empty() || v == *"-" || (self.is_float && v.parse::<f64>().is_ok()) ||
                         v.parse::<i64>().is_ok() {
@@ -9,11 +9,12 @@ use ratatui::prelude::{Line, Span, Stylize};
 pub enum EditorStatus {
     Continue,
     Cancel,
+    #[allow(dead_code)]
     Finished{option: TemplateOption}
 }
 
 pub trait OptionUi {
-    fn render_list_item(&self) -> Result<ListItem> {
+    fn render_list_item(&self) -> Result<ListItem<'_>> {
         let prompt = self.get_option().get_prompt();
         let value = self.get_option().get_value();
 
@@ -91,7 +91,7 @@ impl OptionUi for TextUI {
                     self.status = EditorStatus::Cancel;
                     return Ok(())
                 }else if KeyCode::Enter == key.code && self.is_valid() {
-                    let v = self.input.value().clone().to_string();
+                    let v = self.input.value().to_string();
                     let o = self.option.clone().set_value(v);
                     self.option = o.clone();
                     self.status = EditorStatus::Finished { option: o};
@@ -0,0 +1,56 @@
+use std::io::Stdout;
+use std::time::Duration;
+use crossterm::event;
+use crossterm::event::{Event, KeyCode};
+use ratatui::backend::CrosstermBackend;
+use ratatui::prelude::{Color, Span, Style, Stylize};
+use ratatui::Terminal;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+pub fn trust_menu(terminal: &mut Terminal<CrosstermBackend<Stdout>>, name: &str, commands: &[String]) -> anyhow::Result<bool> {
+    let mut text = vec![
+        Line::from(vec![Span::raw("This template wants to run the following commands on your machine:").yellow()]),
+        Line::from(""),
+    ];
+
+    for command in commands {
+        text.push(Line::from(vec![Span::raw(" - ").gray(), Span::raw(command.clone()).white()]));
+    }
+
+    text.push(Line::from(""));
+    text.push(Line::from(vec![Span::raw("Only trust templates from people you trust. Prefab will remember your answer until the template changes.").gray()]));
+
+    loop {
+        terminal.draw(|frame| {
+            let paragraph = Paragraph::new(text.clone())
+                .block(Block::default().title(Line::from(vec![
+                    Span::raw("[").gray(),
+                    Span::raw("Trust:"),
+                    Span::raw(name).yellow(),
+                    Span::raw("]").gray(),
+                    Span::raw("──"),
+                    Span::raw("[").gray(),
+                    Span::raw("y").blue(),
+                    Span::raw("-Trust "),
+                    Span::raw("n/Esc").blue(),
+                    Span::raw("-Cancel"),
+                    Span::raw("]").gray(),
+                ])).borders(Borders::ALL))
+                .wrap(Wrap { trim: false })
+                .style(Style::default().fg(Color::White));
+
+            frame.render_widget(paragraph, frame.size())
+        })?;
+
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(true),
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => return Ok(false),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use std::{path::Path, fs, process::Command, str::from_utf8, collections::HashMap};
-use anyhow::{Ok, Result};
+use std::{path::{Path, PathBuf}, fs, process::{Child, Command, Stdio}, collections::HashMap, io::{BufRead, BufReader, Read, Write}, thread, time::{Duration, Instant}};
+use anyhow::{anyhow, Ok, Result};
 use tera::{Tera, Context};
+use thiserror::Error;
 
 pub fn copy_all(source: impl AsRef<Path>, destination: impl AsRef<Path>, tera: &mut Tera, ctx: &Context) -> Result<()>
 {
@@ -15,36 +16,175 @@ pub fn copy_all(source: impl AsRef<Path>, destination: impl AsRef<Path>, tera: &
         let e = entry?;
         let templated_name = tera.render_str(e.file_name().to_str().as_ref().unwrap(),ctx)?;
         if e.file_type()?.is_file() {
-            fs::copy(e.path(), &destination.as_ref().join(&templated_name))?;
+            fs::copy(e.path(), destination.as_ref().join(&templated_name))?;
         }
 
         if e.file_type()?.is_dir() {
-            copy_all(&source.as_ref().join(e.file_name()), destination.as_ref().join(&templated_name), tera, ctx)?;
+            copy_all(source.as_ref().join(e.file_name()), destination.as_ref().join(&templated_name), tera, ctx)?;
         }
     }
 
     Ok(())
 }
 
-pub fn exec(command: &str, environment: HashMap<String, String>, working_directory: impl AsRef<Path>) -> Result<String>{
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-                .current_dir(working_directory)
-                .envs(environment)
-                .args(["/C", command])
-                .output()
-                .expect("failed to execute process")
+/// Lists every file below a folder as paths relative to it, sorted so the order is stable.
+pub fn list_files(root: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    let mut folders = vec![PathBuf::new()];
+
+    while let Some(folder) = folders.pop() {
+        for entry in fs::read_dir(root.as_ref().join(&folder))? {
+            let e = entry?;
+
+            if e.file_name() == ".git" {
+                continue;
+            }
+
+            if e.file_type()?.is_dir() {
+                folders.push(folder.join(e.file_name()));
+            } else {
+                result.push(folder.join(e.file_name()));
+            }
+        }
+    }
+
+    result.sort();
+    Ok(result)
+}
+
+#[derive(Error, Debug)]
+pub enum ExecError {
+    #[error("Command `{command}` failed with exit code {code}!\n{stderr}")]
+    Failed {
+        command: String,
+        code: String,
+        stderr: String,
+        stdout: String,
+    },
+    #[error("Command `{command}` timed out after {seconds} seconds!")]
+    TimedOut {
+        command: String,
+        seconds: u64,
+    },
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ExecOptions {
+    /// When set output is streamed to the terminal as it arrives with each line prefixed by this.
+    pub prefix: Option<String>,
+    /// Kills the command and everything it started if it runs longer than this.
+    pub timeout: Option<Duration>,
+    /// Text written to the command's stdin.
+    pub stdin: Option<String>,
+}
+
+pub fn exec_with(command: &str, environment: HashMap<String, String>, working_directory: impl AsRef<Path>, options: &ExecOptions) -> Result<String>{
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
     } else {
-        Command::new("sh")
-                .envs(environment)
-                .current_dir(working_directory)
-                .arg("-c")
-                .arg(command)
-                .output()?
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
     };
 
-    let output = from_utf8(&output.stdout)?;
-    Ok(output.to_string())
+    cmd.envs(environment)
+        .current_dir(working_directory)
+        .stdin(if options.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    //Put the command in its own process group so the whole tree can be killed on timeout
+    #[cfg(unix)]
+    if options.timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let mut child = cmd.spawn()?;
+
+    if let (Some(input), Some(mut stdin)) = (options.stdin.clone(), child.stdin.take()) {
+        //Written from a thread so a command that doesn't read its input can't block us
+        thread::spawn(move || stdin.write_all(input.as_bytes()));
+    }
+
+    let stdout = child.stdout.take().ok_or(anyhow!("Failed to capture stdout of command!"))?;
+    let stderr = child.stderr.take().ok_or(anyhow!("Failed to capture stderr of command!"))?;
+
+    let stdout_prefix = options.prefix.clone();
+    let stderr_prefix = options.prefix.clone();
+    let stdout_reader = thread::spawn(move || read_stream(stdout, stdout_prefix, false));
+    let stderr_reader = thread::spawn(move || read_stream(stderr, stderr_prefix, true));
+
+    let status = if let Some(timeout) = options.timeout {
+        let started = Instant::now();
+
+        loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+
+            if started.elapsed() >= timeout {
+                kill_tree(&mut child);
+                return Err(ExecError::TimedOut { command: command.to_string(), seconds: timeout.as_secs() }.into());
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+    } else {
+        child.wait()?
+    };
+
+    let output = stdout_reader.join().map_err(|_| anyhow!("Failed to read stdout of command!"))?;
+    let errors = stderr_reader.join().map_err(|_| anyhow!("Failed to read stderr of command!"))?;
+
+    if !status.success() {
+        let code = status.code().map(|c| c.to_string()).unwrap_or("none (killed by signal)".to_string());
+        return Err(ExecError::Failed { command: command.to_string(), code, stderr: errors.trim_end().to_string(), stdout: output }.into());
+    }
+
+    Ok(output)
+}
+
+fn read_stream(stream: impl Read, prefix: Option<String>, is_stderr: bool) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut result = vec![];
+    let mut line = vec![];
+
+    while let Result::Ok(count) = reader.read_until(b'\n', &mut line) {
+        if count == 0 {
+            break;
+        }
+
+        if let Some(prefix) = &prefix {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\r', '\n']);
+
+            if is_stderr {
+                eprintln!("[{}] {}", prefix, text);
+            } else {
+                println!("[{}] {}", prefix, text);
+            }
+        }
+
+        result.append(&mut line);
+    }
+
+    String::from_utf8_lossy(&result).to_string()
+}
+
+fn kill_tree(child: &mut Child) {
+    let pid = child.id().to_string();
+
+    if cfg!(target_os = "windows") {
+        let _ = Command::new("taskkill").args(["/T", "/F", "/PID", &pid]).output();
+    } else {
+        let _ = Command::new("kill").args(["-KILL", &format!("-{}", pid)]).output();
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
 }
 
 #[cfg(test)]
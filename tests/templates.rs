@@ -23,7 +23,7 @@ fn can_load_template(){
     f = { Float = { prompt = "Test floar", value = 1.0 }}
     "#).unwrap();
 
-    template::Template::load(template_directory.path().clone()).unwrap();
+    template::Template::load(template_directory.path()).unwrap();
 }
 
 #[test]
@@ -50,8 +50,8 @@ fn can_generate_static_files_from_template() {
     {{a}}
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    template.apply(target_directory.path().clone()).unwrap();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
 
     let mut file1_path = PathBuf::new();
     let mut file2_path = PathBuf::new();
@@ -95,8 +95,8 @@ fn can_generate_template() {
     {{a}}
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    template.apply(target_directory.path().clone()).unwrap();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
 
     let mut file1_path = PathBuf::new();
     let mut file2_path = PathBuf::new();
@@ -134,8 +134,8 @@ fn can_call_system_in_template() {
     {{ system(command="bash -c 'echo hi'") }}
     "#).unwrap();
     
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    template.apply(target_directory.path().clone()).unwrap();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
 
     let mut file1_path = PathBuf::new();
 
@@ -178,8 +178,8 @@ fn can_replace_template_variables_in_path(){
     {{a}}
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    template.apply(target_directory.path().clone()).unwrap();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
 
     let mut file1_path = PathBuf::new();
     let mut file2_path = PathBuf::new();
@@ -229,8 +229,8 @@ fn can_run_before_hook(){
     echo test > ./test.txt
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    template.apply(target_directory.path().clone()).unwrap();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
 
     let mut result_path = PathBuf::new();
 
@@ -266,8 +266,8 @@ fn can_run_after_hook(){
     echo test > ./test.txt
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    template.apply(target_directory.path().clone()).unwrap();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
 
     let mut result_path = PathBuf::new();
 
@@ -290,8 +290,8 @@ fn can_return_error_if_regex_doesnt_match(){
     c = { Regex = { prompt ="Test Regex", pattern = "abc", value = "foo" }}
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    let result = template.apply(target_directory.path().clone());
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    let result = template.apply(target_directory.path());
 
     assert!(result.is_err());
 }
@@ -307,8 +307,219 @@ fn can_return_error_if_choice_doesnt_match(){
     d = { Choice = { prompt = "Test choice", options = ["a", "b", "c"], value = "d"}}
     "#).unwrap();
 
-    let mut template = template::Template::load(template_directory.path().clone()).unwrap();
-    let result = template.apply(target_directory.path().clone());
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    let result = template.apply(target_directory.path());
 
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn can_read_options_in_hook(){
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+
+    let target_directory = TempDir::new().unwrap();
+    manifest.write_str(r#"
+    after_hook = "echo $PREFAB_OPT_A $PREFAB_OPT_MY_FLAG > ./env.txt && cat $PREFAB_CONTEXT_FILE > ./context.json"
+
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    my-flag = { Boolean = { prompt = "Test Boolean", value = true }}
+    "#).unwrap();
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
+
+    let env_string = fs::read_to_string(target_directory.child("env.txt").path()).unwrap();
+    let context_string = fs::read_to_string(target_directory.child("context.json").path()).unwrap();
+
+    assert_eq!(env_string.trim(), "yo true");
+    assert!(context_string.contains(r#""a":"yo""#));
+    assert!(context_string.contains(r#""my-flag":true"#));
+}
+
+#[test]
+fn can_return_error_if_hook_fails(){
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+
+    let target_directory = TempDir::new().unwrap();
+    manifest.write_str(r#"
+    after_hook = "echo broken >&2 && exit 3"
+
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    let error = template.apply(target_directory.path()).unwrap_err().to_string();
+
+    assert!(error.contains("exit 3"));
+    assert!(error.contains("exit code 3"));
+    assert!(error.contains("broken"));
+}
+
+#[test]
+fn can_kill_hook_after_timeout(){
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+
+    let target_directory = TempDir::new().unwrap();
+    manifest.write_str(r#"
+    before_hook = { command = "sleep 10", timeout = 1 }
+
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    let started = std::time::Instant::now();
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    let error = template.apply(target_directory.path()).unwrap_err().to_string();
+
+    assert!(error.contains("timed out"));
+    assert!(started.elapsed().as_secs() < 5);
+}
+
+#[test]
+fn can_run_script_hooks_from_hooks_folder(){
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+    let pre_script = template_directory.child("hooks/pre_gen.sh");
+    let post_script = template_directory.child("hooks/post_gen");
+
+    let target_directory = TempDir::new().unwrap();
+    manifest.write_str(r#"
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    b = { Boolean = { prompt = "Test Boolean", value = false }}
+    "#).unwrap();
+
+    pre_script.write_str(r#"
+    echo {{ a }} > ./pre.txt
+    "#).unwrap();
+
+    post_script.write_str(r#"#!/bin/sh
+    {% if b %}
+    echo yes > ./post.txt
+    {% else %}
+    echo no > ./post.txt
+    {% endif %}
+    "#).unwrap();
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
+
+    let pre_string = fs::read_to_string(target_directory.child("pre.txt").path()).unwrap();
+    let post_string = fs::read_to_string(target_directory.child("post.txt").path()).unwrap();
+
+    assert_eq!(pre_string.trim(), "yo");
+    assert_eq!(post_string.trim(), "no");
+}
+
+#[test]
+fn can_adjust_options_with_prompt_hook(){
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+
+    let target_directory = TempDir::new().unwrap();
+    manifest.write_str(r#"
+    prompt_hook = "grep -q 'Test choice' && echo '{ \"a\": { \"value\": \"from hook\" }, \"d\": { \"options\": [\"x\", \"y\"], \"hidden\": true } }'"
+
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    d = { Choice = { prompt = "Test choice", options = ["a", "b", "c"], value = "a"}}
+    "#).unwrap();
+
+    let template = template::Template::load(template_directory.path()).unwrap();
+    let options = template.run_prompt_hook(template.get_options(), target_directory.path()).unwrap();
+
+    assert_eq!(options["a"].get_value().unwrap(), "from hook");
+    assert_eq!(options["d"].get_choice_options().unwrap(), vec!["x", "y"]);
+    assert!(options["d"].is_hidden());
+    assert!(!options["a"].is_hidden());
+}
+
+#[test]
+fn can_pass_arguments_to_system(){
+    let template_directory =  TempDir::new().unwrap();
+    let target_directory = TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+    let template_file = template_directory.child("template/myfile.txt");
+
+    manifest.write_str(r#"
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    template_file.write_str(r#"{{ system(command="cat", stdin=a, trim=true) }}|{{ system(command="echo $NAME", env=["NAME=" ~ a], trim=true) }}|{{ system(command="ls template", cwd="template", trim=true) }}|{{ system(command="echo partial; exit 3", allow_failure=true, trim=true) }}"#).unwrap();
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
+
+    let file_string = fs::read_to_string(target_directory.child("myfile.txt").path()).unwrap();
+    assert_eq!(file_string, "yo|yo|myfile.txt|partial");
+}
+
+#[test]
+fn can_report_system_failure(){
+    let template_directory =  TempDir::new().unwrap();
+    let target_directory = TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+    let template_file = template_directory.child("template/myfile.txt");
+
+    manifest.write_str(r#"
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    template_file.write_str(r#"{{ system(command="echo broken >&2; exit 3") }}"#).unwrap();
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    let error = format!("{:#}", template.apply(target_directory.path()).unwrap_err());
+
+    assert!(error.contains("exit code 3"));
+    assert!(error.contains("broken"));
+}
+
+#[test]
+fn can_cache_system_calls_within_apply(){
+    let template_directory =  TempDir::new().unwrap();
+    let target_directory = TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+
+    manifest.write_str(r#"
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    for name in ["one.txt", "two.txt", "three.txt"] {
+        template_directory.child(format!("template/{}", name)).write_str(r#"{{ system(command="echo run >> count.txt", cwd="template") }}"#).unwrap();
+    }
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.apply(target_directory.path()).unwrap();
+
+    let count_string = fs::read_to_string(template_directory.child("count.txt").path()).unwrap();
+    assert_eq!(count_string.lines().count(), 1);
+}
+
+#[test]
+fn can_use_functions_in_option_defaults(){
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+
+    manifest.write_str(r#"
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "{{ env(name='PREFAB_SURELY_NOT_SET', default='from env') }}" }}
+    b = { FreeText = { prompt = "Test FreeText", value = "{{ now(format='%Y') }}" }}
+    c = { FreeText = { prompt = "Test FreeText", value = "plain" }}
+    "#).unwrap();
+
+    let mut template = template::Template::load(template_directory.path()).unwrap();
+    template.render_defaults().unwrap();
+    let options = template.get_options();
+
+    assert_eq!(options["a"].get_value().unwrap(), "from env");
+    assert_eq!(options["b"].get_value().unwrap().len(), 4);
+    assert_eq!(options["c"].get_value().unwrap(), "plain");
+}
@@ -0,0 +1,85 @@
+use prefab::template::Template;
+use prefab::trust::{find_commands, get_template_hash, TrustStore};
+use assert_fs::{TempDir, prelude::PathChild};
+use assert_fs::prelude::*;
+
+#[test]
+fn can_find_commands_in_template() {
+    let template_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+    let template_file = template_directory.child("template/myfile.txt");
+    let script = template_directory.child("hooks/post_gen.sh");
+
+    manifest.write_str(r#"
+    after_hook = "make build"
+
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    template_file.write_str(r#"{{ system(command="git rev-parse HEAD") }}"#).unwrap();
+    script.write_str("echo hi").unwrap();
+
+    let template = Template::load(template_directory.path()).unwrap();
+    let commands = find_commands(&template).unwrap();
+
+    assert!(commands.contains(&"after_hook: make build".to_string()));
+    assert!(commands.contains(&"post_gen: hooks/post_gen.sh".to_string()));
+    assert!(commands.iter().any(|c| c.starts_with("system() in template/myfile.txt") && c.contains("git rev-parse HEAD")));
+}
+
+#[test]
+fn can_remember_trusted_template_until_it_changes() {
+    let template_directory =  TempDir::new().unwrap();
+    let store_directory =  TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+    let store_path = store_directory.child("trusted.toml");
+
+    manifest.write_str(r#"after_hook = "make build""#).unwrap();
+
+    let hash = get_template_hash(template_directory.path()).unwrap();
+    let mut store = TrustStore::load(store_path.path()).unwrap();
+    assert!(!store.is_trusted(&hash));
+
+    store.trust(&hash, template_directory.path());
+    store.save().unwrap();
+
+    let store = TrustStore::load(store_path.path()).unwrap();
+    assert!(store.is_trusted(&hash));
+
+    manifest.write_str(r#"after_hook = "rm -rf /""#).unwrap();
+    let changed_hash = get_template_hash(template_directory.path()).unwrap();
+    assert!(!store.is_trusted(&changed_hash));
+}
+
+#[test]
+fn can_refuse_to_run_commands() {
+    let template_directory =  TempDir::new().unwrap();
+    let target_directory = TempDir::new().unwrap();
+    let manifest = template_directory.child("prefab.toml");
+    let template_file = template_directory.child("template/myfile.txt");
+
+    manifest.write_str(r#"
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+
+    template_file.write_str(r#"{{ system(command="echo hi") }}"#).unwrap();
+
+    let mut template = Template::load(template_directory.path()).unwrap();
+    template.set_allow_exec(false);
+    assert!(template.apply(target_directory.path()).is_err());
+
+    manifest.write_str(r#"
+    before_hook = "echo test > ./test.txt"
+
+    [options]
+    a = { FreeText = { prompt = "Test FreeText", value = "yo" }}
+    "#).unwrap();
+    template_file.write_str("{{ a }}").unwrap();
+
+    let mut template = Template::load(template_directory.path()).unwrap();
+    template.set_allow_exec(false);
+    assert!(template.apply(target_directory.path()).is_err());
+    assert!(!target_directory.child("test.txt").path().exists());
+}